@@ -3,13 +3,42 @@
  * Contains structs, functions, traits, and implementations
  */
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+#[cfg(feature = "argon2")]
+use argon2::{Algorithm, Argon2, Params, Version};
+#[cfg(feature = "argon2")]
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine as _};
 
 // Type alias
 pub type UserId = u64;
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+/// Default Argon2id memory cost in KiB (19 MiB, per current OWASP guidance)
+#[cfg(feature = "argon2")]
+const DEFAULT_ARGON2_MEMORY_KIB: u32 = 19_456;
+
+/// Default Argon2id iteration count
+#[cfg(feature = "argon2")]
+const DEFAULT_ARGON2_ITERATIONS: u32 = 2;
+
+/// Default Argon2id parallelism (lanes)
+#[cfg(feature = "argon2")]
+const DEFAULT_ARGON2_PARALLELISM: u32 = 1;
+
+/// Length in bytes of the derived Argon2id output
+#[cfg(feature = "argon2")]
+const ARGON2_OUTPUT_LEN: usize = 32;
+
+/// Length in bytes of the per-user random salt
+#[cfg(feature = "argon2")]
+const ARGON2_SALT_LEN: usize = 16;
+
 /// User struct with authentication details
 #[derive(Debug, Clone)]
 pub struct User {
@@ -17,6 +46,10 @@ pub struct User {
     pub email: String,
     pub password_hash: String,
     pub role: UserRole,
+    pub privileges: Privileges,
+    /// Name of a [`Role`] in the service's [`RoleStore`], for permission
+    /// checks that don't fit `UserRole`'s fixed ladder
+    pub role_name: Option<String>,
 }
 
 /// User role enumeration
@@ -27,6 +60,88 @@ pub enum UserRole {
     User,
 }
 
+/// A bitmask of named privileges, for cheap subset checks without string
+/// matching or role-ladder comparisons
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Privileges(u64);
+
+/// Assign each named privilege a distinct bit and build a name↔bit lookup table
+macro_rules! define_privileges {
+    ($($name:ident = $bit:expr),* $(,)?) => {
+        impl Privileges {
+            $(pub const $name: Privileges = Privileges(1 << $bit);)*
+
+            const NAMES: &'static [(&'static str, Privileges)] = &[
+                $((stringify!($name), Privileges::$name)),*
+            ];
+        }
+    };
+}
+
+define_privileges! {
+    SYS_AUDIT = 0,
+    SYS_MODIFY = 1,
+    DATASTORE_READ = 2,
+    DATASTORE_BACKUP = 3,
+}
+
+impl Privileges {
+    /// The empty privilege set
+    pub const NONE: Privileges = Privileges(0);
+
+    /// Returns `true` if `self` grants every privilege in `required`
+    pub fn contains(self, required: Privileges) -> bool {
+        self.0 & required.0 == required.0
+    }
+
+    /// Parse a comma-separated list of privilege names, e.g. from a config file
+    pub fn parse(names: &str) -> Result<Privileges> {
+        let mut privileges = Privileges::NONE;
+
+        for name in names.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (_, bit) = Self::NAMES
+                .iter()
+                .find(|(known, _)| *known == name)
+                .ok_or_else(|| format!("unknown privilege `{name}`"))?;
+            privileges |= *bit;
+        }
+
+        Ok(privileges)
+    }
+
+    /// Format this privilege set as a comma-separated list of names
+    pub fn format(self) -> String {
+        Self::NAMES
+            .iter()
+            .filter(|(_, bit)| self.contains(*bit))
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl std::ops::BitOr for Privileges {
+    type Output = Privileges;
+
+    fn bitor(self, rhs: Privileges) -> Privileges {
+        Privileges(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for Privileges {
+    fn bitor_assign(&mut self, rhs: Privileges) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl std::ops::BitAnd for Privileges {
+    type Output = Privileges;
+
+    fn bitand(self, rhs: Privileges) -> Privileges {
+        Privileges(self.0 & rhs.0)
+    }
+}
+
 /// Authentication service trait
 pub trait AuthService {
     fn login(&self, email: &str, password: &str) -> Result<User>;
@@ -34,29 +149,50 @@ pub trait AuthService {
     fn verify_password(&self, password: &str, hash: &str) -> bool;
 }
 
-/// Default authentication service implementation
-pub struct DefaultAuthService {
-    users: HashMap<String, User>,
-    session_timeout: u64,
+/// A session record tracking which user it belongs to and when it was created
+#[derive(Debug, Clone)]
+struct Session {
+    user_id: UserId,
+    created_at: Instant,
 }
 
-impl DefaultAuthService {
-    /// Create a new authentication service
+/// Pluggable backing store for sessions
+pub trait SessionStore: Send + Sync {
+    /// Create a new session for `user_id` and return its session id
+    fn create(&self, user_id: UserId) -> Result<String>;
+
+    /// Look up the user a session belongs to. Returns `None` if the session
+    /// doesn't exist or has exceeded its timeout, evicting it in the latter case.
+    fn lookup(&self, session_id: &str) -> Result<Option<UserId>>;
+
+    /// Revoke a single session
+    fn revoke(&self, session_id: &str) -> Result<()>;
+
+    /// Revoke every session belonging to a user
+    fn revoke_all_for_user(&self, user_id: UserId) -> Result<()>;
+}
+
+/// In-memory `SessionStore` backed by a `HashMap`, evicting sessions whose
+/// age exceeds `timeout_secs` on lookup
+pub struct InMemorySessionStore {
+    sessions: Mutex<HashMap<String, Session>>,
+    timeout_secs: u64,
+}
+
+impl InMemorySessionStore {
+    /// Create a new in-memory session store
     ///
     /// # Arguments
-    /// * `session_timeout` - Session timeout in seconds
-    ///
-    /// # Returns
-    /// A new `DefaultAuthService` instance
-    pub fn new(session_timeout: u64) -> Self {
-        DefaultAuthService {
-            users: HashMap::new(),
-            session_timeout,
+    /// * `timeout_secs` - Session timeout in seconds
+    pub fn new(timeout_secs: u64) -> Self {
+        InMemorySessionStore {
+            sessions: Mutex::new(HashMap::new()),
+            timeout_secs,
         }
     }
 
     /// Generate a random session ID
-    fn generate_session_id(&self) -> String {
+    fn generate_session_id() -> String {
         use rand::Rng;
         let mut rng = rand::thread_rng();
 
@@ -71,11 +207,412 @@ impl DefaultAuthService {
             })
             .collect()
     }
+}
+
+impl SessionStore for InMemorySessionStore {
+    fn create(&self, user_id: UserId) -> Result<String> {
+        let session_id = Self::generate_session_id();
+
+        let mut sessions = self.sessions.lock().map_err(|_| "session store lock poisoned")?;
+        sessions.insert(
+            session_id.clone(),
+            Session {
+                user_id,
+                created_at: Instant::now(),
+            },
+        );
+
+        Ok(session_id)
+    }
+
+    fn lookup(&self, session_id: &str) -> Result<Option<UserId>> {
+        let mut sessions = self.sessions.lock().map_err(|_| "session store lock poisoned")?;
+
+        let Some(session) = sessions.get(session_id) else {
+            return Ok(None);
+        };
+
+        if session.created_at.elapsed().as_secs() > self.timeout_secs {
+            sessions.remove(session_id);
+            return Ok(None);
+        }
+
+        Ok(Some(session.user_id))
+    }
+
+    fn revoke(&self, session_id: &str) -> Result<()> {
+        let mut sessions = self.sessions.lock().map_err(|_| "session store lock poisoned")?;
+        sessions.remove(session_id);
+        Ok(())
+    }
+
+    fn revoke_all_for_user(&self, user_id: UserId) -> Result<()> {
+        let mut sessions = self.sessions.lock().map_err(|_| "session store lock poisoned")?;
+        sessions.retain(|_, session| session.user_id != user_id);
+        Ok(())
+    }
+}
+
+/// Default authentication service implementation
+pub struct DefaultAuthService {
+    users: HashMap<String, User>,
+    session_store: Box<dyn SessionStore>,
+    role_store: RoleStore,
+    passwd_path: Option<PathBuf>,
+    shadow_path: Option<PathBuf>,
+    #[cfg(feature = "argon2")]
+    argon2_memory_kib: u32,
+    #[cfg(feature = "argon2")]
+    argon2_iterations: u32,
+    #[cfg(feature = "argon2")]
+    argon2_parallelism: u32,
+}
+
+impl DefaultAuthService {
+    /// Create a new authentication service
+    ///
+    /// # Arguments
+    /// * `session_timeout` - Session timeout in seconds
+    ///
+    /// # Returns
+    /// A new `DefaultAuthService` instance
+    pub fn new(session_timeout: u64) -> Self {
+        DefaultAuthService {
+            users: HashMap::new(),
+            session_store: Box::new(InMemorySessionStore::new(session_timeout)),
+            role_store: RoleStore::new(),
+            passwd_path: None,
+            shadow_path: None,
+            #[cfg(feature = "argon2")]
+            argon2_memory_kib: DEFAULT_ARGON2_MEMORY_KIB,
+            #[cfg(feature = "argon2")]
+            argon2_iterations: DEFAULT_ARGON2_ITERATIONS,
+            #[cfg(feature = "argon2")]
+            argon2_parallelism: DEFAULT_ARGON2_PARALLELISM,
+        }
+    }
+
+    /// Override the Argon2id cost parameters (defaults: 19 MiB, 2 iterations, 1 lane)
+    #[cfg(feature = "argon2")]
+    pub fn with_argon2_params(mut self, memory_kib: u32, iterations: u32, parallelism: u32) -> Self {
+        self.argon2_memory_kib = memory_kib;
+        self.argon2_iterations = iterations;
+        self.argon2_parallelism = parallelism;
+        self
+    }
+
+    /// Replace the session store, e.g. to swap in a database-backed implementation
+    pub fn with_session_store(mut self, session_store: Box<dyn SessionStore>) -> Self {
+        self.session_store = session_store;
+        self
+    }
+
+    /// Replace the role store used by [`Self::has_role_permission`]
+    pub fn with_role_store(mut self, role_store: RoleStore) -> Self {
+        self.role_store = role_store;
+        self
+    }
+
+    /// Returns `true` if `user`'s role (via [`User::role_name`]) grants `required`
+    /// in this service's [`RoleStore`]. Users with no assigned role name never
+    /// match.
+    pub fn has_role_permission(&self, user: &User, required: &str) -> bool {
+        match &user.role_name {
+            Some(role_name) => self.role_store.check(role_name, required),
+            None => false,
+        }
+    }
+
+    /// Load users from a passwd-style file (`id:email:role` records) and a
+    /// shadow-style file (`email:password_hash` records). On unix, the shadow
+    /// file is written back with `0600` permissions by [`Self::save`]. Blank
+    /// lines and `#` comments are skipped; a malformed record errors with its
+    /// line number.
+    ///
+    /// # Arguments
+    /// * `passwd_path` - Path to the passwd-style file
+    /// * `shadow_path` - Path to the shadow-style file
+    /// * `session_timeout` - Session timeout in seconds
+    pub fn from_files(
+        passwd_path: impl AsRef<Path>,
+        shadow_path: impl AsRef<Path>,
+        session_timeout: u64,
+    ) -> Result<Self> {
+        let passwd_path = passwd_path.as_ref().to_path_buf();
+        let shadow_path = shadow_path.as_ref().to_path_buf();
+
+        let hashes = Self::read_shadow(&shadow_path)?;
+        let records = Self::read_passwd(&passwd_path)?;
+
+        let mut service = DefaultAuthService::new(session_timeout);
+        for (id, email, role) in records {
+            let password_hash = hashes.get(&email).cloned().unwrap_or_default();
+            service.add_user(User {
+                id,
+                email,
+                password_hash,
+                role,
+                privileges: Privileges::NONE,
+                role_name: None,
+            });
+        }
+
+        service.passwd_path = Some(passwd_path);
+        service.shadow_path = Some(shadow_path);
+
+        Ok(service)
+    }
+
+    /// Atomically rewrite the passwd/shadow files this service was loaded from
+    /// via [`DefaultAuthService::from_files`]
+    pub fn save(&self) -> Result<()> {
+        let passwd_path = self
+            .passwd_path
+            .as_ref()
+            .ok_or("no passwd file configured; load this service with `from_files` first")?;
+        let shadow_path = self
+            .shadow_path
+            .as_ref()
+            .ok_or("no shadow file configured; load this service with `from_files` first")?;
+
+        let mut users: Vec<&User> = self.users.values().collect();
+        users.sort_by_key(|user| user.id);
+
+        let mut passwd_contents = String::new();
+        let mut shadow_contents = String::new();
+        for user in users {
+            passwd_contents.push_str(&format!(
+                "{}:{}:{}\n",
+                user.id,
+                user.email,
+                Self::role_to_str(&user.role)
+            ));
+            shadow_contents.push_str(&format!("{}:{}\n", user.email, user.password_hash));
+        }
+
+        let previous_passwd = fs::read_to_string(passwd_path).ok();
+
+        Self::write_atomic(passwd_path, &passwd_contents, false)?;
+        if let Err(err) = Self::write_atomic(shadow_path, &shadow_contents, true) {
+            if let Some(previous) = previous_passwd {
+                Self::write_atomic(passwd_path, &previous, false)?;
+            }
+            return Err(format!("failed to write shadow file, rolled back passwd file: {err}").into());
+        }
+
+        Ok(())
+    }
+
+    /// Read a passwd-style file into `(id, email, role)` records
+    fn read_passwd(path: &Path) -> Result<Vec<(UserId, String, UserRole)>> {
+        let contents = fs::read_to_string(path)?;
+        let mut records = Vec::new();
+
+        for (line_no, line) in contents.lines().enumerate() {
+            let line_no = line_no + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.splitn(3, ':').collect();
+            let [id, email, role] = fields[..] else {
+                return Err(format!(
+                    "malformed passwd record at line {line_no}: expected `id:email:role`"
+                )
+                .into());
+            };
+
+            let id: UserId = id
+                .parse()
+                .map_err(|_| format!("malformed passwd record at line {line_no}: invalid id `{id}`"))?;
+            let role = Self::role_from_str(role).ok_or_else(|| {
+                format!("malformed passwd record at line {line_no}: unknown role `{role}`")
+            })?;
+
+            records.push((id, email.to_string(), role));
+        }
+
+        Ok(records)
+    }
+
+    /// Read a shadow-style file into an `email -> password_hash` map
+    fn read_shadow(path: &Path) -> Result<HashMap<String, String>> {
+        let contents = fs::read_to_string(path)?;
+        let mut hashes = HashMap::new();
+
+        for (line_no, line) in contents.lines().enumerate() {
+            let line_no = line_no + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (email, hash) = line.split_once(':').ok_or_else(|| {
+                format!("malformed shadow record at line {line_no}: expected `email:password_hash`")
+            })?;
+
+            hashes.insert(email.to_string(), hash.to_string());
+        }
+
+        Ok(hashes)
+    }
+
+    fn role_to_str(role: &UserRole) -> &'static str {
+        match role {
+            UserRole::Admin => "admin",
+            UserRole::Moderator => "moderator",
+            UserRole::User => "user",
+        }
+    }
+
+    fn role_from_str(s: &str) -> Option<UserRole> {
+        match s {
+            "admin" => Some(UserRole::Admin),
+            "moderator" => Some(UserRole::Moderator),
+            "user" => Some(UserRole::User),
+            _ => None,
+        }
+    }
+
+    /// Write `contents` to `path` atomically: write to a temp file in the same
+    /// directory, then rename it into place, so a crash mid-write can't
+    /// corrupt the existing file. When `restrict` is set, the file is
+    /// chmod'd to `0600` on unix before the rename.
+    fn write_atomic(path: &Path, contents: &str, restrict: bool) -> Result<()> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        fs::write(&tmp_path, contents)?;
+
+        #[cfg(unix)]
+        if restrict {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600))?;
+        }
+        #[cfg(not(unix))]
+        let _ = restrict;
+
+        fs::rename(&tmp_path, path)?;
+
+        Ok(())
+    }
+
+    /// Hash a plaintext password into a PHC-format Argon2id string
+    /// (`$argon2id$v=19$m=...,t=...,p=...$<b64salt>$<b64hash>`)
+    #[cfg(feature = "argon2")]
+    fn hash_password(&self, password: &str) -> Result<String> {
+        use rand::Rng;
+
+        let mut salt = [0u8; ARGON2_SALT_LEN];
+        rand::thread_rng().fill(&mut salt);
+
+        let params = Params::new(
+            self.argon2_memory_kib,
+            self.argon2_iterations,
+            self.argon2_parallelism,
+            Some(ARGON2_OUTPUT_LEN),
+        )
+        .map_err(|e| format!("invalid argon2 parameters: {e}"))?;
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let mut output = [0u8; ARGON2_OUTPUT_LEN];
+        argon2
+            .hash_password_into(password.as_bytes(), &salt, &mut output)
+            .map_err(|e| format!("argon2 hashing failed: {e}"))?;
+
+        Ok(format!(
+            "$argon2id$v=19$m={},t={},p={}${}${}",
+            self.argon2_memory_kib,
+            self.argon2_iterations,
+            self.argon2_parallelism,
+            STANDARD_NO_PAD.encode(salt),
+            STANDARD_NO_PAD.encode(output),
+        ))
+    }
+
+    /// Parse a PHC-format Argon2id string into its cost parameters, salt and hash
+    #[cfg(feature = "argon2")]
+    fn parse_phc(hash: &str) -> Option<(u32, u32, u32, Vec<u8>, Vec<u8>)> {
+        let mut parts = hash.split('$');
+        let _empty = parts.next().filter(|s| s.is_empty())?;
+        let algorithm = parts.next()?;
+        if algorithm != "argon2id" {
+            return None;
+        }
+        let _version = parts.next()?;
+        let params = parts.next()?;
+        let salt_b64 = parts.next()?;
+        let hash_b64 = parts.next()?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        let mut memory_kib = None;
+        let mut iterations = None;
+        let mut parallelism = None;
+        for kv in params.split(',') {
+            let (key, value) = kv.split_once('=')?;
+            let value: u32 = value.parse().ok()?;
+            match key {
+                "m" => memory_kib = Some(value),
+                "t" => iterations = Some(value),
+                "p" => parallelism = Some(value),
+                _ => return None,
+            }
+        }
+
+        let salt = STANDARD_NO_PAD.decode(salt_b64).ok()?;
+        let expected = STANDARD_NO_PAD.decode(hash_b64).ok()?;
+
+        Some((memory_kib?, iterations?, parallelism?, salt, expected))
+    }
+
+    /// Set (or reset) a user's password, deriving and storing a fresh Argon2id hash
+    ///
+    /// # Arguments
+    /// * `email` - Email of the user to update
+    /// * `password` - New plaintext password
+    #[cfg(feature = "argon2")]
+    pub fn set_password(&mut self, email: &str, password: &str) -> Result<()> {
+        let hash = self.hash_password(password)?;
+        let user = self.users.get_mut(email).ok_or("User not found")?;
+        user.password_hash = hash;
+        Ok(())
+    }
+
+    /// Set (or reset) a user's password
+    ///
+    /// # Arguments
+    /// * `email` - Email of the user to update
+    /// * `password` - New plaintext password
+    #[cfg(not(feature = "argon2"))]
+    pub fn set_password(&mut self, email: &str, password: &str) -> Result<()> {
+        let user = self.users.get_mut(email).ok_or("User not found")?;
+        user.password_hash = password.to_string();
+        Ok(())
+    }
 
     /// Add a user to the service
     pub fn add_user(&mut self, user: User) {
         self.users.insert(user.email.clone(), user);
     }
+
+    /// Look up the user a session belongs to, via the injected `SessionStore`
+    pub fn lookup_session(&self, session_id: &str) -> Result<Option<UserId>> {
+        self.session_store.lookup(session_id)
+    }
+
+    /// Revoke a single session, via the injected `SessionStore`
+    pub fn revoke_session(&self, session_id: &str) -> Result<()> {
+        self.session_store.revoke(session_id)
+    }
+
+    /// Revoke every session belonging to a user, via the injected `SessionStore`
+    pub fn revoke_all_sessions_for_user(&self, user_id: UserId) -> Result<()> {
+        self.session_store.revoke_all_for_user(user_id)
+    }
 }
 
 impl AuthService for DefaultAuthService {
@@ -95,23 +632,58 @@ impl AuthService for DefaultAuthService {
         Ok(user.clone())
     }
 
-    /// Create a new session for the user
+    /// Create a new session for the user, delegating to the injected `SessionStore`
     fn create_session(&self, user_id: UserId) -> Result<String> {
-        let session_id = self.generate_session_id();
+        self.session_store.create(user_id)
+    }
 
-        // In a real implementation, store session in database
-        println!("Created session {} for user {}", session_id, user_id);
+    /// Verify password against hash
+    #[cfg(feature = "argon2")]
+    fn verify_password(&self, password: &str, hash: &str) -> bool {
+        let Some((memory_kib, iterations, parallelism, salt, expected)) = Self::parse_phc(hash)
+        else {
+            return false;
+        };
 
-        Ok(session_id)
+        let params = match Params::new(memory_kib, iterations, parallelism, Some(expected.len())) {
+            Ok(params) => params,
+            Err(_) => return false,
+        };
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let mut output = vec![0u8; expected.len()];
+        if argon2
+            .hash_password_into(password.as_bytes(), &salt, &mut output)
+            .is_err()
+        {
+            return false;
+        }
+
+        constant_time_eq(&output, &expected)
     }
 
     /// Verify password against hash
+    #[cfg(not(feature = "argon2"))]
     fn verify_password(&self, password: &str, hash: &str) -> bool {
         // Simplified for testing - in production use bcrypt or argon2
         password == hash
     }
 }
 
+/// Compare two byte slices in constant time, regardless of where they first differ
+#[cfg(feature = "argon2")]
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
 /// Check if user has required permission
 ///
 /// # Arguments
@@ -132,6 +704,166 @@ pub fn has_permission(user: &User, required_role: &UserRole) -> bool {
     role_hierarchy(&user.role) >= role_hierarchy(required_role)
 }
 
+/// Check if user has required permission, via a `Privileges` bitmask
+///
+/// # Arguments
+/// * `user` - The user to check
+/// * `required` - The required privileges
+///
+/// # Returns
+/// `true` if `user`'s privileges are a superset of `required`
+pub fn has_permission_privileges(user: &User, required: Privileges) -> bool {
+    user.privileges.contains(required)
+}
+
+/// A named role with optional parent roles and dotted-glob permission patterns
+///
+/// Permissions are strings like `lab.test.*` or `lab.some.admin`; a `*` segment
+/// matches any single dotted segment, and a trailing `*` matches any remainder.
+#[derive(Debug, Clone)]
+pub struct Role {
+    pub name: String,
+    pub parents: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+/// A collection of roles that resolves inherited, wildcard permission grants
+///
+/// Roles are typically loaded once (e.g. via [`RoleStore::from_toml`]) and then
+/// queried with [`RoleStore::check`] on each authorization decision.
+#[derive(Debug, Clone, Default)]
+pub struct RoleStore {
+    roles: HashMap<String, Role>,
+}
+
+impl RoleStore {
+    /// Create an empty role store
+    pub fn new() -> Self {
+        RoleStore {
+            roles: HashMap::new(),
+        }
+    }
+
+    /// Add or replace a role
+    pub fn insert(&mut self, role: Role) {
+        self.roles.insert(role.name.clone(), role);
+    }
+
+    /// Parse a TOML document where each table key is a role name, e.g.:
+    ///
+    /// ```toml
+    /// [viewer]
+    /// permissions = ["lab.test.*"]
+    ///
+    /// [admin]
+    /// parents = ["viewer"]
+    /// permissions = ["lab.some.admin"]
+    /// ```
+    pub fn from_toml(input: &str) -> Result<Self> {
+        let value: toml::Value = input.parse().map_err(|e| format!("invalid role TOML: {e}"))?;
+        let table = value.as_table().ok_or("role TOML must be a table of roles")?;
+
+        let mut store = RoleStore::new();
+        for (name, def) in table {
+            let def = def
+                .as_table()
+                .ok_or_else(|| format!("role `{name}` must be a table"))?;
+
+            let parents = def
+                .get("parents")
+                .map(|v| Self::string_array(v, name, "parents"))
+                .transpose()?
+                .unwrap_or_default();
+            let permissions = def
+                .get("permissions")
+                .map(|v| Self::string_array(v, name, "permissions"))
+                .transpose()?
+                .unwrap_or_default();
+
+            store.insert(Role {
+                name: name.clone(),
+                parents,
+                permissions,
+            });
+        }
+
+        Ok(store)
+    }
+
+    fn string_array(value: &toml::Value, role: &str, field: &str) -> Result<Vec<String>> {
+        let array = value
+            .as_array()
+            .ok_or_else(|| format!("`{field}` of role `{role}` must be an array"))?;
+
+        array
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| format!("`{field}` of role `{role}` must contain strings").into())
+            })
+            .collect()
+    }
+
+    /// Resolve the full permission set granted to `role_name`, transitively
+    /// walking `parents`. Roles that (directly or indirectly) list themselves
+    /// as an ancestor are handled safely via a visited-set cycle guard.
+    pub fn resolve(&self, role_name: &str) -> HashSet<String> {
+        let mut permissions = HashSet::new();
+        let mut visited = HashSet::new();
+        self.resolve_into(role_name, &mut permissions, &mut visited);
+        permissions
+    }
+
+    fn resolve_into(
+        &self,
+        role_name: &str,
+        permissions: &mut HashSet<String>,
+        visited: &mut HashSet<String>,
+    ) {
+        if !visited.insert(role_name.to_string()) {
+            return;
+        }
+
+        let Some(role) = self.roles.get(role_name) else {
+            return;
+        };
+        permissions.extend(role.permissions.iter().cloned());
+
+        for parent in &role.parents {
+            self.resolve_into(parent, permissions, visited);
+        }
+    }
+
+    /// Returns `true` if `role_name`'s resolved permission set grants `required`
+    pub fn check(&self, role_name: &str, required: &str) -> bool {
+        self.resolve(role_name)
+            .iter()
+            .any(|pattern| Self::pattern_matches(pattern, required))
+    }
+
+    /// Match a dotted-glob permission pattern against a requested permission
+    fn pattern_matches(pattern: &str, required: &str) -> bool {
+        let pattern_parts: Vec<&str> = pattern.split('.').collect();
+        let required_parts: Vec<&str> = required.split('.').collect();
+
+        for (i, segment) in pattern_parts.iter().enumerate() {
+            if *segment == "*" && i == pattern_parts.len() - 1 {
+                return true;
+            }
+
+            match required_parts.get(i) {
+                Some(required_segment) if segment == required_segment || *segment == "*" => {
+                    continue
+                }
+                _ => return false,
+            }
+        }
+
+        pattern_parts.len() == required_parts.len()
+    }
+}
+
 /// Calculate cyclomatic complexity metrics for demonstration
 ///
 /// # Arguments
@@ -172,6 +904,8 @@ mod tests {
             email: "admin@test.com".to_string(),
             password_hash: "hash".to_string(),
             role: UserRole::Admin,
+            privileges: Privileges::NONE,
+            role_name: None,
         };
 
         assert!(has_permission(&admin_user, &UserRole::User));
@@ -187,4 +921,265 @@ mod tests {
         assert_eq!(mean, 3.0);
         assert_eq!(max, 5);
     }
+
+    #[cfg(feature = "argon2")]
+    #[test]
+    fn test_set_password_and_verify() {
+        let mut service = DefaultAuthService::new(3600);
+        service.add_user(User {
+            id: 1,
+            email: "user@test.com".to_string(),
+            password_hash: String::new(),
+            role: UserRole::User,
+            privileges: Privileges::NONE,
+            role_name: None,
+        });
+
+        service.set_password("user@test.com", "correct horse battery staple").unwrap();
+        let hash = service.users.get("user@test.com").unwrap().password_hash.clone();
+
+        assert!(hash.starts_with("$argon2id$v=19$"));
+        assert!(service.verify_password("correct horse battery staple", &hash));
+        assert!(!service.verify_password("wrong password", &hash));
+    }
+
+    #[test]
+    fn test_role_store_inheritance_and_wildcards() {
+        let toml = r#"
+            [viewer]
+            permissions = ["lab.test.*"]
+
+            [admin]
+            parents = ["viewer"]
+            permissions = ["lab.some.admin"]
+        "#;
+        let store = RoleStore::from_toml(toml).unwrap();
+
+        assert!(store.check("viewer", "lab.test.run"));
+        assert!(!store.check("viewer", "lab.some.admin"));
+        assert!(store.check("admin", "lab.test.run"));
+        assert!(store.check("admin", "lab.some.admin"));
+        assert!(!store.check("admin", "lab.other.admin"));
+    }
+
+    #[test]
+    fn test_role_store_cycle_guard() {
+        let mut store = RoleStore::new();
+        store.insert(Role {
+            name: "a".to_string(),
+            parents: vec!["b".to_string()],
+            permissions: vec!["a.read".to_string()],
+        });
+        store.insert(Role {
+            name: "b".to_string(),
+            parents: vec!["a".to_string()],
+            permissions: vec!["b.read".to_string()],
+        });
+
+        let resolved = store.resolve("a");
+        assert!(resolved.contains("a.read"));
+        assert!(resolved.contains("b.read"));
+    }
+
+    #[test]
+    fn test_has_role_permission() {
+        let mut store = RoleStore::new();
+        store.insert(Role {
+            name: "editor".to_string(),
+            parents: Vec::new(),
+            permissions: vec!["lab.test.*".to_string()],
+        });
+
+        let service = DefaultAuthService::new(3600).with_role_store(store);
+        let editor = User {
+            id: 1,
+            email: "editor@test.com".to_string(),
+            password_hash: "hash".to_string(),
+            role: UserRole::User,
+            privileges: Privileges::NONE,
+            role_name: Some("editor".to_string()),
+        };
+        let no_role = User {
+            id: 2,
+            email: "norole@test.com".to_string(),
+            password_hash: "hash".to_string(),
+            role: UserRole::User,
+            privileges: Privileges::NONE,
+            role_name: None,
+        };
+
+        assert!(service.has_role_permission(&editor, "lab.test.run"));
+        assert!(!service.has_role_permission(&editor, "lab.other.admin"));
+        assert!(!service.has_role_permission(&no_role, "lab.test.run"));
+    }
+
+    #[test]
+    fn test_session_store_lookup_and_revoke() {
+        let store = InMemorySessionStore::new(3600);
+
+        let session_id = store.create(42).unwrap();
+        assert_eq!(store.lookup(&session_id).unwrap(), Some(42));
+
+        store.revoke(&session_id).unwrap();
+        assert_eq!(store.lookup(&session_id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_session_store_revoke_all_for_user() {
+        let store = InMemorySessionStore::new(3600);
+
+        let first = store.create(1).unwrap();
+        let second = store.create(1).unwrap();
+        let other = store.create(2).unwrap();
+
+        store.revoke_all_for_user(1).unwrap();
+
+        assert_eq!(store.lookup(&first).unwrap(), None);
+        assert_eq!(store.lookup(&second).unwrap(), None);
+        assert_eq!(store.lookup(&other).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_privileges_union_and_contains() {
+        let granted = Privileges::SYS_AUDIT | Privileges::DATASTORE_READ;
+
+        assert!(granted.contains(Privileges::SYS_AUDIT));
+        assert!(granted.contains(Privileges::DATASTORE_READ));
+        assert!(!granted.contains(Privileges::DATASTORE_BACKUP));
+        assert!(granted.contains(Privileges::SYS_AUDIT | Privileges::DATASTORE_READ));
+    }
+
+    #[test]
+    fn test_privileges_parse_and_format() {
+        let parsed = Privileges::parse("SYS_AUDIT, DATASTORE_BACKUP").unwrap();
+
+        assert!(parsed.contains(Privileges::SYS_AUDIT));
+        assert!(parsed.contains(Privileges::DATASTORE_BACKUP));
+        assert!(Privileges::parse("NOT_A_PRIVILEGE").is_err());
+        assert_eq!(parsed.format(), "SYS_AUDIT,DATASTORE_BACKUP");
+    }
+
+    #[test]
+    fn test_has_permission_privileges() {
+        let mut user = User {
+            id: 1,
+            email: "user@test.com".to_string(),
+            password_hash: "hash".to_string(),
+            role: UserRole::User,
+            privileges: Privileges::NONE,
+            role_name: None,
+        };
+
+        assert!(!has_permission_privileges(&user, Privileges::DATASTORE_BACKUP));
+
+        user.privileges = Privileges::DATASTORE_READ | Privileges::DATASTORE_BACKUP;
+        assert!(has_permission_privileges(&user, Privileges::DATASTORE_BACKUP));
+        assert!(!has_permission_privileges(&user, Privileges::SYS_MODIFY));
+    }
+
+    fn unique_temp_path(name: &str) -> PathBuf {
+        let suffix = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("cindex_test_{name}_{suffix}"))
+    }
+
+    #[test]
+    fn test_from_files_and_save_round_trip() {
+        let passwd_path = unique_temp_path("passwd");
+        let shadow_path = unique_temp_path("shadow");
+
+        fs::write(
+            &passwd_path,
+            "# comment\n\n1:admin@test.com:admin\n2:user@test.com:user\n",
+        )
+        .unwrap();
+        fs::write(&shadow_path, "admin@test.com:hash1\nuser@test.com:hash2\n").unwrap();
+
+        let mut service = DefaultAuthService::from_files(&passwd_path, &shadow_path, 3600).unwrap();
+        let admin = service.users.get("admin@test.com").unwrap();
+        assert_eq!(admin.id, 1);
+        assert_eq!(admin.role, UserRole::Admin);
+        assert_eq!(admin.password_hash, "hash1");
+
+        service.add_user(User {
+            id: 3,
+            email: "new@test.com".to_string(),
+            password_hash: "hash3".to_string(),
+            role: UserRole::Moderator,
+            privileges: Privileges::NONE,
+            role_name: None,
+        });
+        service.save().unwrap();
+
+        let reloaded = DefaultAuthService::from_files(&passwd_path, &shadow_path, 3600).unwrap();
+        assert_eq!(reloaded.users.len(), 3);
+        assert_eq!(
+            reloaded.users.get("new@test.com").unwrap().role,
+            UserRole::Moderator
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&shadow_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_file(&passwd_path).unwrap();
+        fs::remove_file(&shadow_path).unwrap();
+    }
+
+    #[test]
+    fn test_save_rolls_back_passwd_on_shadow_write_failure() {
+        let passwd_path = unique_temp_path("rollback_passwd");
+        let shadow_path = unique_temp_path("rollback_shadow");
+
+        fs::write(&passwd_path, "1:admin@test.com:admin\n").unwrap();
+        fs::write(&shadow_path, "admin@test.com:hash1\n").unwrap();
+
+        let mut service = DefaultAuthService::from_files(&passwd_path, &shadow_path, 3600).unwrap();
+
+        // Add a user so the passwd file written by `save` differs from the
+        // original; the assertion below then only holds if it was rolled back.
+        service.add_user(User {
+            id: 2,
+            email: "user@test.com".to_string(),
+            password_hash: "hash2".to_string(),
+            role: UserRole::User,
+            privileges: Privileges::NONE,
+            role_name: None,
+        });
+
+        // Replace the shadow file with a directory, so write_atomic's final
+        // `fs::rename` of its temp file onto shadow_path fails.
+        fs::remove_file(&shadow_path).unwrap();
+        fs::create_dir(&shadow_path).unwrap();
+
+        let original_passwd = fs::read_to_string(&passwd_path).unwrap();
+        let result = service.save();
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&passwd_path).unwrap(), original_passwd);
+
+        fs::remove_file(&passwd_path).unwrap();
+        fs::remove_dir(&shadow_path).unwrap();
+    }
+
+    #[test]
+    fn test_from_files_rejects_malformed_record() {
+        let passwd_path = unique_temp_path("bad_passwd");
+        let shadow_path = unique_temp_path("bad_shadow");
+
+        fs::write(&passwd_path, "1:admin@test.com:admin\nnot-a-valid-line\n").unwrap();
+        fs::write(&shadow_path, "").unwrap();
+
+        let err = DefaultAuthService::from_files(&passwd_path, &shadow_path, 3600)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("line 2"));
+
+        fs::remove_file(&passwd_path).unwrap();
+        fs::remove_file(&shadow_path).unwrap();
+    }
 }